@@ -1,5 +1,18 @@
 use borsh::{BorshSerialize, BorshDeserialize};
 
+/// Outcome of a `roundtrip_test_case` run, reported instead of panicking so
+/// a driver can run hundreds of cases in one process and collect a
+/// pass/fail matrix rather than dying on the first divergence.
+#[repr(u8)]
+pub enum TestResult {
+    Ok = 0,
+    DeserializeError = 1,
+    AssertMismatch = 2,
+    UnknownId = 3,
+    Panicked = 4,
+    NonCanonicalEncoding = 5,
+}
+
 // Each `id` corresponds to a specific test case, this function is supposed to import the given object, create a new object based on the `id` it receives, assert these two objects are equal,
 // and export the object it created back to the caller.
 /// # Safety
@@ -12,14 +25,20 @@ pub unsafe extern "C" fn roundtrip_test_case(
     input_len: usize,
     output: *mut *mut u8,
     output_len: *mut usize,
-) {
+) -> u8 {
     unsafe {
         let input = std::slice::from_raw_parts(input, input_len);
-        let mut out = run_test(id, input);
-        *output = out.as_mut_ptr();
-        *output_len = out.len();
-        std::mem::forget(out);
-    };
+        match std::panic::catch_unwind(|| run_test(id, input)) {
+            Ok(Ok(mut out)) => {
+                *output = out.as_mut_ptr();
+                *output_len = out.len();
+                std::mem::forget(out);
+                TestResult::Ok as u8
+            }
+            Ok(Err(code)) => code as u8,
+            Err(_) => TestResult::Panicked as u8,
+        }
+    }
 }
 
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
@@ -30,7 +49,97 @@ struct TestCase0 {
     data: Vec<i32>,
 }
 
-fn run_test(id: u8, input: &[u8]) -> Vec<u8> {
+/// A vector bounded to at most `CAPACITY` elements. Encodes identically to
+/// `Vec<T>` — a little-endian `u32` length prefix followed by the elements,
+/// never widened to `u64` — so a fixed upper bound on size doesn't change
+/// the wire format, only the set of lengths a decoder should accept.
+#[derive(PartialEq, Debug)]
+struct FixedVec<T, const CAPACITY: usize>(Vec<T>);
+
+impl<T, const CAPACITY: usize> FixedVec<T, CAPACITY> {
+    fn new(items: Vec<T>) -> Self {
+        assert!(items.len() <= CAPACITY);
+        FixedVec(items)
+    }
+}
+
+impl<T: BorshSerialize, const CAPACITY: usize> BorshSerialize for FixedVec<T, CAPACITY> {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.0.serialize(writer)
+    }
+}
+
+impl<T: BorshDeserialize, const CAPACITY: usize> BorshDeserialize for FixedVec<T, CAPACITY> {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let items = Vec::<T>::deserialize_reader(reader)?;
+        if items.len() > CAPACITY {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "length exceeds fixed capacity",
+            ));
+        }
+        Ok(FixedVec(items))
+    }
+}
+
+/// A packed bitset: a little-endian `u32` bit count followed by
+/// `ceil(count / 8)` bytes, bit `i` at byte `i / 8` bit `i % 8`. Unlike
+/// `Vec<bool>` (one byte per element), the payload is densely packed, so
+/// this exercises the same length-prefix invariant — `u32`, not `u64`,
+/// and a bare `00 00 00 00` with no payload when empty — over a
+/// differently shaped body.
+#[derive(PartialEq, Debug)]
+struct BitVec {
+    bits: Vec<bool>,
+}
+
+impl BorshSerialize for BitVec {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        (self.bits.len() as u32).serialize(writer)?;
+        let mut bytes = vec![0u8; self.bits.len().div_ceil(8)];
+        for (i, bit) in self.bits.iter().enumerate() {
+            if *bit {
+                bytes[i / 8] |= 1 << (i % 8);
+            }
+        }
+        writer.write_all(&bytes)
+    }
+}
+
+impl BorshDeserialize for BitVec {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let len = u32::deserialize_reader(reader)? as usize;
+        let byte_len = len.div_ceil(8);
+
+        // `len` is attacker-controlled; read in bounded chunks instead of
+        // eagerly allocating `byte_len` bytes up front, so a huge declared
+        // length fails on early EOF instead of committing to a large
+        // allocation before we've seen that many bytes actually exist.
+        const CHUNK: usize = 8192;
+        let mut bytes = Vec::with_capacity(byte_len.min(CHUNK));
+        let mut remaining = byte_len;
+        let mut chunk = [0u8; CHUNK];
+        while remaining > 0 {
+            let take = remaining.min(CHUNK);
+            reader.read_exact(&mut chunk[..take])?;
+            bytes.extend_from_slice(&chunk[..take]);
+            remaining -= take;
+        }
+
+        let bits = (0..len).map(|i| bytes[i / 8] & (1 << (i % 8)) != 0).collect();
+        Ok(BitVec { bits })
+    }
+}
+
+const FIXED_CAPACITY: usize = 4;
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+struct TestCase1 {
+    names: FixedVec<String, FIXED_CAPACITY>,
+    flags: BitVec,
+}
+
+fn run_test(id: u8, input: &[u8]) -> Result<Vec<u8>, TestResult> {
     match id {
         0 => run_case(input, TestCase0{
             name: "ccccc".to_owned(),
@@ -38,16 +147,332 @@ fn run_test(id: u8, input: &[u8]) -> Vec<u8> {
             prob: 0.69,
             data: vec![31, 69],
         }),
-        _ => panic!("unknown id: {}", id),
+        // Empty: both collections must round-trip to a bare `00 00 00 00`
+        // length prefix with no payload bytes following.
+        1 => run_case(input, TestCase1 {
+            names: FixedVec::new(vec![]),
+            flags: BitVec { bits: vec![] },
+        }),
+        // One element: the smallest non-empty length, to catch an
+        // off-by-one in the payload size.
+        2 => run_case(input, TestCase1 {
+            names: FixedVec::new(vec!["a".to_owned()]),
+            flags: BitVec { bits: vec![true] },
+        }),
+        // Capacity-filled: the largest length `FixedVec` will accept, paired
+        // with a bit count that isn't a multiple of 8 to exercise the
+        // partial final byte of the packed bitset.
+        3 => run_case(input, TestCase1 {
+            names: FixedVec::new(vec!["a".to_owned(), "bb".to_owned(), "ccc".to_owned(), "dddd".to_owned()]),
+            flags: BitVec { bits: vec![true, false, true, true, false, true, true] },
+        }),
+        _ => Err(TestResult::UnknownId),
+    }
+}
+
+fn run_case<T: BorshSerialize + BorshDeserialize + PartialEq + std::fmt::Debug>(input: &[u8], output: T) -> Result<Vec<u8>, TestResult> {
+    let decoded: T = borsh::from_slice(input).map_err(|_| TestResult::DeserializeError)?;
+
+    if decoded != output {
+        return Err(TestResult::AssertMismatch);
+    }
+
+    // Borsh guarantees a bijective mapping between values and bytes: re-encoding
+    // what we just decoded must reproduce `input` byte-for-byte. A decoded value
+    // that is semantically equal but non-canonically encoded (wrong length-prefix
+    // width, padded integers, reordered map keys, ...) would pass the equality
+    // check above yet fail this one — report it distinctly from a genuinely
+    // wrong value so a caller can tell the two failure modes apart.
+    let reencoded = borsh::to_vec(&decoded).map_err(|_| TestResult::DeserializeError)?;
+    if reencoded != input {
+        return Err(TestResult::NonCanonicalEncoding);
     }
+
+    let output = borsh::to_vec(&output).map_err(|_| TestResult::DeserializeError)?;
+
+    Ok(output)
 }
 
-fn run_case<T: BorshSerialize + BorshDeserialize + PartialEq + std::fmt::Debug>(input: &[u8], output: T) -> Vec<u8> {
-    let input: T = borsh::from_slice(input).unwrap();
+// Unlike `roundtrip_test_case`, these cases feed deliberately malformed (or
+// otherwise adversarial) Borsh input to the reference decoder and report
+// whether it was rejected, so the Zig side can be checked for matching
+// behavior on the same bytes.
+/// # Safety
+///
+/// There is no safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn reject_test_case(
+    id: u8,
+    input: *const u8,
+    input_len: usize,
+    result_code: *mut u8,
+) {
+    unsafe {
+        let input = std::slice::from_raw_parts(input, input_len);
+        *result_code = run_reject_test(id, input) as u8;
+    };
+}
+
+/// What a given adversarial case is expected to do when fed to the
+/// reference decoder.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Expectation {
+    /// The input is malformed and the decoder must fail rather than OOM or panic.
+    Reject,
+    /// The input is valid (if unusual) and the decoder must succeed cheaply.
+    Accept,
+}
+
+/// Result of comparing a case's actual outcome against its `Expectation`.
+#[repr(u8)]
+enum RejectOutcome {
+    /// The decoder rejected the input, as expected.
+    RejectedAsExpected = 0,
+    /// The decoder accepted input that should have been rejected.
+    AcceptedUnexpectedly = 1,
+    /// The decoder rejected input that should have been accepted.
+    WrongError = 2,
+    /// The decoder accepted the input, as expected.
+    AcceptedAsExpected = 3,
+    /// `id` did not match a known adversarial case.
+    UnknownId = 4,
+}
+
+// A length-prefixed `Vec<i32>` whose declared length claims far more
+// elements than the buffer could possibly hold. The reference decoder must
+// allocate cautiously and fail on early EOF rather than eagerly calling
+// `Vec::with_capacity(len)` on an attacker-controlled length.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+struct VecLenHazard {
+    data: Vec<i32>,
+}
 
-    assert_eq!(input, output);
+// Same hazard as `VecLenHazard`, but for `String`'s length-prefixed byte
+// count.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+struct StringLenHazard {
+    text: String,
+}
 
-    let output = borsh::to_vec(&output).unwrap();
+// Same hazard again, for `HashMap`'s entry count.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+struct MapLenHazard {
+    data: std::collections::HashMap<u8, u8>,
+}
 
-    return output;
+// `Vec<T>` where `size_of::<T>() == 0`. borsh (1.8.0) refuses any non-empty
+// declared length for a zero-sized element outright — "Collections of
+// zero-sized types are not allowed due to deny-of-service concerns on
+// deserialization" — rather than looping `len` times, so this must be
+// rejected, not accepted cheaply.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+struct ZstVecHazard {
+    data: Vec<()>,
+}
+
+fn run_reject_test(id: u8, input: &[u8]) -> RejectOutcome {
+    match id {
+        0 => run_reject_case::<VecLenHazard>(input, Expectation::Reject),
+        1 => run_reject_case::<StringLenHazard>(input, Expectation::Reject),
+        2 => run_reject_case::<MapLenHazard>(input, Expectation::Reject),
+        3 => run_reject_case::<ZstVecHazard>(input, Expectation::Reject),
+        // Negative control: a well-formed `VecLenHazard` whose declared length
+        // matches its actual payload. Confirms the oracle isn't just rejecting
+        // everything it's handed.
+        4 => run_reject_case::<VecLenHazard>(input, Expectation::Accept),
+        _ => RejectOutcome::UnknownId,
+    }
+}
+
+fn run_reject_case<T: BorshDeserialize>(input: &[u8], expected: Expectation) -> RejectOutcome {
+    match (borsh::from_slice::<T>(input), expected) {
+        (Ok(_), Expectation::Accept) => RejectOutcome::AcceptedAsExpected,
+        (Ok(_), Expectation::Reject) => RejectOutcome::AcceptedUnexpectedly,
+        (Err(_), Expectation::Reject) => RejectOutcome::RejectedAsExpected,
+        (Err(_), Expectation::Accept) => RejectOutcome::WrongError,
+    }
+}
+
+/// An upper bound on the number of bytes a value's Borsh encoding can
+/// occupy, or `Unbounded` if that can't be determined statically (the type
+/// contains a dynamically sized container, or the recurrence overflowed or
+/// cycled back through a type already being computed).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SizeBound {
+    Bounded(usize),
+    Unbounded,
+}
+
+impl SizeBound {
+    fn add(self, other: SizeBound) -> SizeBound {
+        match (self, other) {
+            (SizeBound::Bounded(a), SizeBound::Bounded(b)) => {
+                a.checked_add(b).map_or(SizeBound::Unbounded, SizeBound::Bounded)
+            }
+            _ => SizeBound::Unbounded,
+        }
+    }
+
+    fn mul(self, n: usize) -> SizeBound {
+        match self {
+            SizeBound::Bounded(a) => a.checked_mul(n).map_or(SizeBound::Unbounded, SizeBound::Bounded),
+            SizeBound::Unbounded => SizeBound::Unbounded,
+        }
+    }
+
+    fn max(self, other: SizeBound) -> SizeBound {
+        match (self, other) {
+            (SizeBound::Bounded(a), SizeBound::Bounded(b)) => SizeBound::Bounded(a.max(b)),
+            _ => SizeBound::Unbounded,
+        }
+    }
+}
+
+/// A type whose Borsh encoding has a statically derivable size bound,
+/// computed by a recurrence modelled on `BorshSchema`'s: fixed-width
+/// primitives contribute their byte width, `[T; N]` contributes `N *
+/// size(T)`, structs sum their fields, enums contribute `1 +
+/// max(variant)`, `Option<T>` contributes `1 + size(T)`, and dynamically
+/// sized containers (`String`, `Vec<T>`, `HashMap`/`HashSet`) are
+/// unbounded.
+///
+/// `seen` tracks the types currently being computed so a type that
+/// recurses through itself (e.g. a `Box<Self>` field) reports `Unbounded`
+/// instead of overflowing the stack.
+trait MaxSize: 'static {
+    fn max_size(seen: &mut Vec<std::any::TypeId>) -> SizeBound;
+}
+
+/// Guards a `MaxSize::max_size` body against recursing back into a type
+/// that's already on the stack.
+fn enter<T: 'static>(
+    seen: &mut Vec<std::any::TypeId>,
+    compute: impl FnOnce(&mut Vec<std::any::TypeId>) -> SizeBound,
+) -> SizeBound {
+    let id = std::any::TypeId::of::<T>();
+    if seen.contains(&id) {
+        return SizeBound::Unbounded;
+    }
+    seen.push(id);
+    let bound = compute(seen);
+    seen.pop();
+    bound
+}
+
+macro_rules! impl_max_size_fixed {
+    ($($t:ty => $n:expr),* $(,)?) => {
+        $(impl MaxSize for $t {
+            fn max_size(_seen: &mut Vec<std::any::TypeId>) -> SizeBound {
+                SizeBound::Bounded($n)
+            }
+        })*
+    };
+}
+
+impl_max_size_fixed! {
+    bool => 1,
+    u8 => 1, i8 => 1,
+    u16 => 2, i16 => 2,
+    u32 => 4, i32 => 4, f32 => 4,
+    u64 => 8, i64 => 8, f64 => 8,
+    u128 => 16, i128 => 16,
+}
+
+impl<T: MaxSize> MaxSize for Option<T> {
+    fn max_size(seen: &mut Vec<std::any::TypeId>) -> SizeBound {
+        enter::<Self>(seen, |seen| SizeBound::Bounded(1).add(T::max_size(seen)))
+    }
+}
+
+impl<T: MaxSize, const N: usize> MaxSize for [T; N] {
+    fn max_size(seen: &mut Vec<std::any::TypeId>) -> SizeBound {
+        enter::<Self>(seen, |seen| T::max_size(seen).mul(N))
+    }
+}
+
+impl<T: 'static> MaxSize for Vec<T> {
+    fn max_size(_seen: &mut Vec<std::any::TypeId>) -> SizeBound {
+        SizeBound::Unbounded
+    }
+}
+
+impl MaxSize for String {
+    fn max_size(_seen: &mut Vec<std::any::TypeId>) -> SizeBound {
+        SizeBound::Unbounded
+    }
+}
+
+impl<K: 'static, V: 'static> MaxSize for std::collections::HashMap<K, V> {
+    fn max_size(_seen: &mut Vec<std::any::TypeId>) -> SizeBound {
+        SizeBound::Unbounded
+    }
+}
+
+impl<T: 'static> MaxSize for std::collections::HashSet<T> {
+    fn max_size(_seen: &mut Vec<std::any::TypeId>) -> SizeBound {
+        SizeBound::Unbounded
+    }
+}
+
+/// Exercises the enum arm of the `MaxSize` recurrence: a unit variant, a
+/// tuple variant, and a struct variant, each contributing their own size
+/// under the shared `1 + max(variant)` discriminant bound.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+enum Shape {
+    Point,
+    Circle(f64),
+    Square { side: f64 },
+}
+
+impl MaxSize for Shape {
+    fn max_size(seen: &mut Vec<std::any::TypeId>) -> SizeBound {
+        enter::<Self>(seen, |seen| {
+            SizeBound::Bounded(1) // discriminant
+                .add(
+                    SizeBound::Bounded(0) // Point
+                        .max(f64::max_size(seen)) // Circle(f64)
+                        .max(f64::max_size(seen)), // Square { side: f64 }
+                )
+        })
+    }
+}
+
+impl MaxSize for TestCase0 {
+    fn max_size(seen: &mut Vec<std::any::TypeId>) -> SizeBound {
+        enter::<Self>(seen, |seen| {
+            String::max_size(seen) // name
+                .add(u128::max_size(seen)) // age
+                .add(f64::max_size(seen)) // prob
+                .add(Vec::<i32>::max_size(seen)) // data
+        })
+    }
+}
+
+/// # Safety
+///
+/// There is no safety
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn max_serialized_size(id: u8, out_bound: *mut usize, out_is_bounded: *mut bool) {
+    unsafe {
+        match compute_max_serialized_size(id) {
+            SizeBound::Bounded(bound) => {
+                *out_bound = bound;
+                *out_is_bounded = true;
+            }
+            SizeBound::Unbounded => {
+                *out_bound = 0;
+                *out_is_bounded = false;
+            }
+        }
+    };
+}
+
+fn compute_max_serialized_size(id: u8) -> SizeBound {
+    let mut seen = Vec::new();
+    match id {
+        0 => TestCase0::max_size(&mut seen),
+        1 => Shape::max_size(&mut seen),
+        2 => std::collections::HashSet::<u8>::max_size(&mut seen),
+        _ => SizeBound::Unbounded,
+    }
 }